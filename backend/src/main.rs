@@ -1,13 +1,30 @@
 // main.rs
 
-use actix_web::{middleware, web, App, HttpResponse, HttpServer, Responder};
+use actix::{Actor, AsyncContext, StreamHandler};
+use actix_web::{middleware, web, App, Error, HttpRequest, HttpResponse, HttpServer, Responder};
+use actix_web_actors::ws;
 use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Mutex, RwLock};
+use tokio::sync::broadcast;
+
+// Default location for the persisted counter state; override with the
+// COUNTER_STATE_PATH environment variable (e.g. to point at a mounted volume).
+const DEFAULT_STATE_PATH: &str = "state.json";
+
+// Default bounds; override with the MAX_INCREMENT / MAX_COUNTER_VALUE
+// environment variables. MAX_COUNTER_VALUE is a symmetric per-counter bound
+// (a change that would move a counter outside [-max, max] is rejected, and
+// each counter is checked independently), not an aggregate cap across all
+// counters.
+const DEFAULT_MAX_INCREMENT: i64 = 10_000;
+const DEFAULT_MAX_COUNTER_VALUE: i64 = 1_000_000;
 
 // Data structures for request and response
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 struct InputData {
-    value: u32,
+    value: i64,
 }
 
 #[derive(Serialize)]
@@ -15,9 +32,137 @@ struct OutputData {
     message: String,
 }
 
+// Typed error body returned for rejected requests (bad bounds or malformed JSON)
+#[derive(Serialize, Deserialize)]
+struct ErrorResponse {
+    error: String,
+    code: u16,
+}
+
+// On-disk representation of the counter state, loaded at startup and
+// rewritten after every mutation.
+#[derive(Default, Serialize, Deserialize)]
+struct PersistedState {
+    counters: HashMap<String, i64>,
+}
+
 // Shared application state
 struct AppState {
-    counter: Mutex<u32>,
+    // The map shape (adding a new counter name) is guarded by the RwLock;
+    // updating an existing counter's value only needs a shared read lock
+    // since the value itself is a lock-free atomic.
+    counters: RwLock<HashMap<String, AtomicI64>>,
+    counter_tx: broadcast::Sender<(String, i64)>,
+    state_path: String,
+    max_increment: i64,
+    // Symmetric per-counter bound: a counter is rejected once it would land
+    // outside [-max_counter_value, max_counter_value]; this is not a cap on
+    // the sum of all counters.
+    max_counter_value: i64,
+    // Serializes writes to `state_path` so concurrent persists can't both write
+    // the same temp file at once and produce a torn file before the rename.
+    persist_lock: Mutex<()>,
+}
+
+// Loads persisted counter state from `path`, starting empty if the file is
+// missing or unreadable (e.g. first run).
+fn load_state(path: &str) -> HashMap<String, AtomicI64> {
+    let persisted: PersistedState = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default();
+    persisted
+        .counters
+        .into_iter()
+        .map(|(name, value)| (name, AtomicI64::new(value)))
+        .collect()
+}
+
+// Serializes the current counters and atomically replaces `path`: write to a
+// temp file in the same directory, then rename over the target so a crash
+// mid-write never leaves a torn file behind.
+fn save_state(path: &str, counters: &HashMap<String, AtomicI64>) -> std::io::Result<()> {
+    let persisted = PersistedState {
+        counters: counters
+            .iter()
+            .map(|(name, value)| (name.clone(), value.load(Ordering::Relaxed)))
+            .collect(),
+    };
+    let json = serde_json::to_string_pretty(&persisted)?;
+    let tmp_path = format!("{}.tmp", path);
+    std::fs::write(&tmp_path, json)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+// Persists the current state, logging (but not failing the request) if the
+// write itself fails. Writes are serialized through `persist_lock` so two
+// concurrent callers can't race on the same temp file.
+fn persist(data: &web::Data<AppState>) {
+    let _guard = data.persist_lock.lock().unwrap();
+    let counters = data.counters.read().unwrap();
+    if let Err(err) = save_state(&data.state_path, &counters) {
+        eprintln!("failed to persist counter state to {}: {}", data.state_path, err);
+    }
+}
+
+// WebSocket actor that pushes counter updates to a single connected client
+struct CounterWs {
+    state: web::Data<AppState>,
+}
+
+// Internal message used to relay broadcast values onto the actor's context
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+struct CounterUpdate(String, i64);
+
+impl Actor for CounterWs {
+    type Context = ws::WebsocketContext<Self>;
+
+    // Subscribe to counter updates as soon as the socket is open. The loop
+    // stops itself once the actor's mailbox is gone (client disconnected),
+    // so this task doesn't outlive the socket it was relaying updates to.
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let mut rx = self.state.counter_tx.subscribe();
+        let addr = ctx.address();
+        actix::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok((name, value)) => {
+                        if addr.try_send(CounterUpdate(name, value)).is_err() {
+                            break;
+                        }
+                    }
+                    // We fell too far behind the broadcast buffer; skip the
+                    // gap and keep relaying rather than giving up entirely.
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+}
+
+impl actix::Handler<CounterUpdate> for CounterWs {
+    type Result = ();
+
+    fn handle(&mut self, msg: CounterUpdate, ctx: &mut Self::Context) {
+        ctx.text(format!("{}:{}", msg.0, msg.1));
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for CounterWs {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => ctx.pong(&msg),
+            Ok(ws::Message::Text(text)) => ctx.text(text),
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            _ => (),
+        }
+    }
 }
 
 // Handler for the root route
@@ -25,28 +170,168 @@ async fn index() -> impl Responder {
     HttpResponse::Ok().body("Welcome to the Rust Actix Web Backend!")
 }
 
-// Handler to get the current counter value
-async fn get_counter(data: web::Data<AppState>) -> impl Responder {
-    let counter = data.counter.lock().unwrap();
+// Handler to get the current value of a named counter
+async fn get_counter(data: web::Data<AppState>, name: web::Path<String>) -> impl Responder {
+    let counters = data.counters.read().unwrap();
+    let value = counters
+        .get(name.as_str())
+        .map(|c| c.load(Ordering::Relaxed))
+        .unwrap_or(0);
     let response = OutputData {
-        message: format!("Current counter value: {}", *counter),
+        message: format!("Current value of '{}': {}", name, value),
     };
     HttpResponse::Ok().json(response)
 }
 
-// Handler to increment the counter
+// Handler to list every known counter and its value
+async fn list_counters(data: web::Data<AppState>) -> impl Responder {
+    let counters = data.counters.read().unwrap();
+    let snapshot: HashMap<&str, i64> = counters
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.load(Ordering::Relaxed)))
+        .collect();
+    HttpResponse::Ok().json(snapshot)
+}
+
+// Handler to increment a named counter
 async fn increment_counter(
     data: web::Data<AppState>,
+    name: web::Path<String>,
+    json: web::Json<InputData>,
+) -> impl Responder {
+    if json.value < 0 {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!(
+                "increment value must be non-negative, got {}; use /decrement to lower a counter",
+                json.value
+            ),
+            code: 400,
+        });
+    }
+    match apply_checked_delta(&data, &name, json.value) {
+        Ok(_) => {
+            persist(&data);
+            let response = OutputData {
+                message: format!("Counter '{}' incremented by {}", name, json.value),
+            };
+            HttpResponse::Ok().json(response)
+        }
+        Err(err) => err,
+    }
+}
+
+// Handler to decrement a named counter
+async fn decrement_counter(
+    data: web::Data<AppState>,
+    name: web::Path<String>,
     json: web::Json<InputData>,
 ) -> impl Responder {
-    let mut counter = data.counter.lock().unwrap();
-    *counter += json.value;
+    let delta = match json.value.checked_neg() {
+        Some(delta) => delta,
+        None => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("value {} cannot be negated for a decrement", json.value),
+                code: 400,
+            })
+        }
+    };
+    match apply_checked_delta(&data, &name, delta) {
+        Ok(_) => {
+            persist(&data);
+            let response = OutputData {
+                message: format!("Counter '{}' decremented by {}", name, json.value),
+            };
+            HttpResponse::Ok().json(response)
+        }
+        Err(err) => err,
+    }
+}
+
+// Applies `delta` to the named counter, creating it on first use. The
+// per-request delta is checked up front; the symmetric per-counter bound
+// (max_counter_value, independent per counter — not an aggregate cap) is
+// enforced with `fetch_update` so concurrent requests can't both pass a
+// stale read of the current value and push the counter out of range.
+fn apply_checked_delta(
+    data: &web::Data<AppState>,
+    name: &str,
+    delta: i64,
+) -> Result<i64, HttpResponse> {
+    if delta.abs() > data.max_increment {
+        return Err(HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!(
+                "delta {} exceeds the maximum allowed per-request change of {}",
+                delta, data.max_increment
+            ),
+            code: 400,
+        }));
+    }
+
+    let max_counter_value = data.max_counter_value;
+    let try_update = |counter: &AtomicI64| {
+        counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+            let new = current.checked_add(delta)?;
+            (new.abs() <= max_counter_value).then_some(new)
+        })
+    };
+
+    let counters = data.counters.read().unwrap();
+    let result = if let Some(counter) = counters.get(name) {
+        try_update(counter)
+    } else {
+        drop(counters);
+        let mut counters = data.counters.write().unwrap();
+        let counter = counters
+            .entry(name.to_string())
+            .or_insert_with(|| AtomicI64::new(0));
+        try_update(counter)
+    };
+
+    match result {
+        Ok(previous) => {
+            let new_value = previous + delta;
+            let _ = data.counter_tx.send((name.to_string(), new_value));
+            Ok(new_value)
+        }
+        Err(_) => Err(HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!(
+                "counter '{}' would move outside the allowed range of [-{}, {}]",
+                name, data.max_counter_value, data.max_counter_value
+            ),
+            code: 400,
+        })),
+    }
+}
+
+// Handler to reset a named counter back to zero
+async fn reset_counter(data: web::Data<AppState>, name: web::Path<String>) -> impl Responder {
+    {
+        let counters = data.counters.read().unwrap();
+        if let Some(counter) = counters.get(name.as_str()) {
+            counter.store(0, Ordering::Relaxed);
+        } else {
+            drop(counters);
+            let mut counters = data.counters.write().unwrap();
+            counters.insert(name.to_string(), AtomicI64::new(0));
+        }
+    }
+    persist(&data);
+    let _ = data.counter_tx.send((name.to_string(), 0));
     let response = OutputData {
-        message: format!("Counter incremented by {}", json.value),
+        message: format!("Counter '{}' reset to 0", name),
     };
     HttpResponse::Ok().json(response)
 }
 
+// Handler that upgrades the connection to a WebSocket pushing live counter values
+async fn counter_ws(
+    req: HttpRequest,
+    stream: web::Payload,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, Error> {
+    ws::start(CounterWs { state: data.clone() }, &req, stream)
+}
+
 // Main function to start the server
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -55,23 +340,164 @@ async fn main() -> std::io::Result<()> {
 
     println!("Starting server at http://{}", server_address);
 
-    // Initialize shared state
+    // Initialize shared state, restoring counters persisted from a previous run
+    let state_path =
+        std::env::var("COUNTER_STATE_PATH").unwrap_or_else(|_| DEFAULT_STATE_PATH.to_string());
+    let max_increment = std::env::var("MAX_INCREMENT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_INCREMENT);
+    let max_counter_value = std::env::var("MAX_COUNTER_VALUE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_COUNTER_VALUE);
+    let (counter_tx, _) = broadcast::channel(16);
     let app_state = web::Data::new(AppState {
-        counter: Mutex::new(0),
+        counters: RwLock::new(load_state(&state_path)),
+        counter_tx,
+        state_path,
+        max_increment,
+        max_counter_value,
+        persist_lock: Mutex::new(()),
     });
 
     // Start HTTP server
     HttpServer::new(move || {
+        // Reject malformed request bodies with the same typed error shape the
+        // handlers use for bounds violations, instead of Actix's default error
+        let json_config = web::JsonConfig::default().error_handler(|err, _req| {
+            let response = ErrorResponse {
+                error: err.to_string(),
+                code: 400,
+            };
+            actix_web::error::InternalError::from_response(
+                err,
+                HttpResponse::BadRequest().json(response),
+            )
+            .into()
+        });
+
         App::new()
             .wrap(middleware::Logger::default()) // Enable logging
             .app_data(app_state.clone()) // Add shared state
+            .app_data(json_config)
             // Configure routes
             .route("/", web::get().to(index))
-            .route("/counter", web::get().to(get_counter))
-            .route("/counter/increment", web::post().to(increment_counter))
+            .route("/counters", web::get().to(list_counters))
+            // Actix matches routes in registration order with no static-over-dynamic
+            // priority, so /counter/ws must come before the dynamic /counter/{name}
+            // or it gets captured as a request for a counter literally named "ws".
+            .route("/counter/ws", web::get().to(counter_ws))
+            .route("/counter/{name}", web::get().to(get_counter))
+            .route("/counter/{name}/increment", web::post().to(increment_counter))
+            .route("/counter/{name}/decrement", web::post().to(decrement_counter))
+            .route("/counter/{name}/reset", web::post().to(reset_counter))
     })
     .bind(server_address)?
     .run()
     .await
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::test;
+
+    // Builds an AppState with deterministic limits and a caller-owned state
+    // file path, bypassing the environment-variable lookups in `main`.
+    fn test_state(max_increment: i64, max_counter_value: i64, state_path: String) -> AppState {
+        let (counter_tx, _) = broadcast::channel(16);
+        AppState {
+            counters: RwLock::new(HashMap::new()),
+            counter_tx,
+            state_path,
+            max_increment,
+            max_counter_value,
+            persist_lock: Mutex::new(()),
+        }
+    }
+
+    fn unique_state_path(label: &str) -> String {
+        std::env::temp_dir()
+            .join(format!(
+                "web3-polkadot-blog-counter-state-{}-{}.json",
+                label,
+                std::process::id()
+            ))
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn save_and_load_state_round_trips_the_counter_map() {
+        let path = unique_state_path("round-trip");
+
+        let mut counters = HashMap::new();
+        counters.insert("votes".to_string(), AtomicI64::new(42));
+        counters.insert("visits".to_string(), AtomicI64::new(-7));
+        save_state(&path, &counters).expect("save_state should succeed");
+
+        let loaded = load_state(&path);
+        assert_eq!(loaded.get("votes").unwrap().load(Ordering::Relaxed), 42);
+        assert_eq!(loaded.get("visits").unwrap().load(Ordering::Relaxed), -7);
+        assert!(!std::path::Path::new(&format!("{}.tmp", path)).exists());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn load_state_starts_empty_when_the_file_is_missing() {
+        let path = unique_state_path("missing");
+        let _ = std::fs::remove_file(&path);
+
+        let loaded = load_state(&path);
+        assert!(loaded.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn increment_over_max_delta_is_rejected_with_typed_error() {
+        let state = web::Data::new(test_state(10, 1_000, unique_state_path("bounds")));
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route("/counter/{name}/increment", web::post().to(increment_counter)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/counter/demo/increment")
+            .set_json(&InputData { value: 50 })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: ErrorResponse = test::read_body_json(resp).await;
+        assert_eq!(body.code, 400);
+        assert!(body.error.contains("exceeds"));
+    }
+
+    #[actix_web::test]
+    async fn counter_ws_route_is_not_shadowed_by_the_dynamic_counter_route() {
+        let state = web::Data::new(test_state(10_000, 1_000_000, unique_state_path("ws-route")));
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route("/counter/ws", web::get().to(counter_ws))
+                .route("/counter/{name}", web::get().to(get_counter)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/counter/ws")
+            .insert_header(("connection", "upgrade"))
+            .insert_header(("upgrade", "websocket"))
+            .insert_header(("sec-websocket-version", "13"))
+            .insert_header(("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ=="))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), StatusCode::SWITCHING_PROTOCOLS);
+    }
+}